@@ -0,0 +1,24 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn main() {
+    println!("cargo:rerun-if-changed=shaders/dungeon.comp");
+
+    let compiler = shaderc::Compiler::new().expect("Failed to create shader compiler");
+    let source = fs::read_to_string("shaders/dungeon.comp").expect("Failed to read dungeon.comp");
+
+    let binary_result = compiler
+        .compile_into_spirv(
+            &source,
+            shaderc::ShaderKind::Compute,
+            "dungeon.comp",
+            "main",
+            None,
+        )
+        .expect("Failed to compile dungeon.comp");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out_dir.join("dungeon.comp.spv"), binary_result.as_binary_u8())
+        .expect("Failed to write dungeon.comp.spv");
+}
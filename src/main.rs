@@ -3,23 +3,38 @@
 use anyhow::{Context, Result};
 use ash::{
     self,
+    ext::debug_utils,
+    khr::{surface, swapchain},
     vk::{
         self, make_api_version, ApplicationInfo, Buffer, BufferCreateInfo, CommandBuffer,
         CommandBufferAllocateInfo, CommandBufferBeginInfo, CommandBufferUsageFlags, CommandPool,
-        CommandPoolCreateInfo, DebugUtilsMessengerCreateInfoEXT, DeviceCreateInfo,
-        DeviceQueueCreateInfo, Fence, FenceCreateFlags, FenceCreateInfo, InstanceCreateInfo,
-        MemoryRequirements, PhysicalDevice, Queue, SubmitInfo,
+        CommandPoolCreateInfo, ComputePipelineCreateInfo, DebugUtilsMessengerCreateInfoEXT,
+        DebugUtilsMessengerEXT, DescriptorBufferInfo, DescriptorPool, DescriptorPoolCreateInfo,
+        DescriptorPoolSize, DescriptorSet, DescriptorSetAllocateInfo, DescriptorSetLayout,
+        DescriptorSetLayoutBinding, DescriptorSetLayoutCreateInfo, DescriptorType,
+        DeviceCreateInfo, DeviceQueueCreateInfo, Extent2D, Fence, FenceCreateFlags,
+        FenceCreateInfo, Image, InstanceCreateInfo, MemoryRequirements, Pipeline,
+        PipelineBindPoint, PipelineLayout, PipelineLayoutCreateInfo, PipelineShaderStageCreateInfo,
+        PhysicalDevice, PhysicalDeviceType, PushConstantRange, Queue, QueueFlags, Semaphore,
+        ShaderModule, ShaderModuleCreateInfo, ShaderStageFlags, SubmitInfo, SurfaceKHR,
+        SwapchainKHR, WriteDescriptorSet,
     },
     Device, Entry, Instance,
 };
 use gpu_allocator::vulkan::*;
 use gpu_allocator::MemoryLocation;
+use log::{debug, error, trace, warn};
 use rand::Rng;
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use std::os::raw::c_void;
 use std::ptr;
 use std::time;
+use winit::event::{ElementState, Event, KeyEvent, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use winit::window::{Window, WindowBuilder};
 use std::{time::Instant, u64};
 
 const VALIDATION_ENABLED: bool = cfg!(debug_assertions);
@@ -56,27 +71,800 @@ unsafe extern "system" fn vulkan_debug_utils_callback(
         _ => "[Unknown]",
     };
     let message = CStr::from_ptr((*p_callback_data).p_message);
-    println!("[Debug]{}{}{:?}", severity, types, message);
+
+    match message_severity {
+        vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => error!("{}{}{:?}", severity, types, message),
+        vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => warn!("{}{}{:?}", severity, types, message),
+        vk::DebugUtilsMessageSeverityFlagsEXT::INFO => debug!("{}{}{:?}", severity, types, message),
+        _ => trace!("{}{}{:?}", severity, types, message),
+    }
 
     vk::FALSE
 }
 
+// The chosen GPU plus the queue family confirmed to support compute and transfer.
+struct PhysicalDeviceSelection {
+    physical_device: PhysicalDevice,
+    compute_queue_family_index: u32,
+}
+
+// Picks the first queue family supporting COMPUTE|TRANSFER on the best-scoring device.
+fn pick_physical_device(instance: &Instance) -> Result<PhysicalDeviceSelection> {
+    let physical_devices = unsafe { instance.enumerate_physical_devices() }
+        .context("Failed to enumerate physical devices")?;
+
+    let required_flags = QueueFlags::COMPUTE | QueueFlags::TRANSFER;
+
+    let mut best: Option<(i32, PhysicalDeviceSelection)> = None;
+    for physical_device in physical_devices {
+        let queue_family_properties =
+            unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+
+        let compute_queue_family_index = queue_family_properties
+            .iter()
+            .position(|properties| properties.queue_flags.contains(required_flags))
+            .map(|index| index as u32);
+
+        let Some(compute_queue_family_index) = compute_queue_family_index else {
+            continue;
+        };
+
+        let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+        let score = match properties.device_type {
+            PhysicalDeviceType::DISCRETE_GPU => 2,
+            PhysicalDeviceType::INTEGRATED_GPU => 1,
+            _ => 0,
+        };
+
+        if best.as_ref().map_or(true, |(best_score, _)| score > *best_score) {
+            best = Some((
+                score,
+                PhysicalDeviceSelection {
+                    physical_device,
+                    compute_queue_family_index,
+                },
+            ));
+        }
+    }
+
+    best.map(|(_, selection)| selection)
+        .context("No physical device with a COMPUTE|TRANSFER queue family found")
+}
+
+// Whether this device has a memory type that's both DEVICE_LOCAL and HOST_VISIBLE.
+fn device_local_memory_is_host_visible(instance: &Instance, physical_device: PhysicalDevice) -> bool {
+    let memory_properties = unsafe { instance.get_physical_device_memory_properties(physical_device) };
+    memory_properties.memory_types[..memory_properties.memory_type_count as usize]
+        .iter()
+        .any(|memory_type| {
+            memory_type.property_flags.contains(
+                vk::MemoryPropertyFlags::DEVICE_LOCAL | vk::MemoryPropertyFlags::HOST_VISIBLE,
+            )
+        })
+}
+
+// Must match the push_constants block in shaders/dungeon.comp
+#[repr(C)]
+struct DungeonPushConstants {
+    width: u32,
+    height: u32,
+    seed: u32,
+    cell_size: u32,
+}
+
+// Grouped so `main` can tear it all down in one place.
+struct ComputePipelineResources {
+    shader_module: ShaderModule,
+    descriptor_set_layout: DescriptorSetLayout,
+    descriptor_pool: DescriptorPool,
+    descriptor_set: DescriptorSet,
+    pipeline_layout: PipelineLayout,
+    pipeline: Pipeline,
+}
+
+// Builds the compute pipeline that writes the generated dungeon into `output_buffer`.
+fn create_compute_pipeline(
+    device: &Device,
+    output_buffer: Buffer,
+) -> Result<ComputePipelineResources> {
+    let shader_module: ShaderModule = {
+        let shader_code: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/dungeon.comp.spv"));
+        let shader_words =
+            ash::util::read_spv(&mut std::io::Cursor::new(shader_code)).context("Invalid SPIR-V")?;
+        let create_info = ShaderModuleCreateInfo::default().code(&shader_words);
+        unsafe { device.create_shader_module(&create_info, None) }?
+    };
+
+    let descriptor_set_layout: DescriptorSetLayout = {
+        let bindings = [DescriptorSetLayoutBinding::default()
+            .binding(0)
+            .descriptor_type(DescriptorType::STORAGE_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(ShaderStageFlags::COMPUTE)];
+        let create_info = DescriptorSetLayoutCreateInfo::default().bindings(&bindings);
+        unsafe { device.create_descriptor_set_layout(&create_info, None) }?
+    };
+
+    let pipeline_layout: PipelineLayout = {
+        let set_layouts = [descriptor_set_layout];
+        let push_constant_ranges = [PushConstantRange::default()
+            .stage_flags(ShaderStageFlags::COMPUTE)
+            .offset(0)
+            .size(std::mem::size_of::<DungeonPushConstants>() as u32)];
+        let create_info = PipelineLayoutCreateInfo::default()
+            .set_layouts(&set_layouts)
+            .push_constant_ranges(&push_constant_ranges);
+        unsafe { device.create_pipeline_layout(&create_info, None) }?
+    };
+
+    let pipeline: Pipeline = {
+        let entry_point = CString::new("main").unwrap();
+        let stage = PipelineShaderStageCreateInfo::default()
+            .stage(ShaderStageFlags::COMPUTE)
+            .module(shader_module)
+            .name(&entry_point);
+        let create_info = ComputePipelineCreateInfo::default()
+            .stage(stage)
+            .layout(pipeline_layout);
+
+        unsafe {
+            device.create_compute_pipelines(vk::PipelineCache::null(), &[create_info], None)
+        }
+        .map_err(|(_, err)| err)
+        .context("Failed to create the dungeon compute pipeline")?
+        .into_iter()
+        .next()
+        .context("No compute pipeline returned")?
+    };
+
+    let descriptor_pool: DescriptorPool = {
+        let pool_sizes = [DescriptorPoolSize::default()
+            .ty(DescriptorType::STORAGE_BUFFER)
+            .descriptor_count(1)];
+        let create_info = DescriptorPoolCreateInfo::default()
+            .max_sets(1)
+            .pool_sizes(&pool_sizes);
+        unsafe { device.create_descriptor_pool(&create_info, None) }?
+    };
+
+    let descriptor_set: DescriptorSet = {
+        let set_layouts = [descriptor_set_layout];
+        let allocate_info = DescriptorSetAllocateInfo::default()
+            .descriptor_pool(descriptor_pool)
+            .set_layouts(&set_layouts);
+        unsafe { device.allocate_descriptor_sets(&allocate_info) }?
+            .into_iter()
+            .next()
+            .context("No descriptor set allocated")?
+    };
+
+    {
+        let buffer_info = [DescriptorBufferInfo::default()
+            .buffer(output_buffer)
+            .offset(0)
+            .range(vk::WHOLE_SIZE)];
+        let write = WriteDescriptorSet::default()
+            .dst_set(descriptor_set)
+            .dst_binding(0)
+            .descriptor_type(DescriptorType::STORAGE_BUFFER)
+            .buffer_info(&buffer_info);
+        unsafe { device.update_descriptor_sets(&[write], &[]) };
+    }
+
+    Ok(ComputePipelineResources {
+        shader_module,
+        descriptor_set_layout,
+        descriptor_pool,
+        descriptor_set,
+        pipeline_layout,
+        pipeline,
+    })
+}
+
+struct SwapchainBundle {
+    swapchain_loader: swapchain::Device,
+    swapchain: SwapchainKHR,
+    images: Vec<Image>,
+    extent: Extent2D,
+    image_available_semaphore: Semaphore,
+}
+
+// Builds (or rebuilds, via `old_swapchain`) the swapchain for `surface`.
+fn create_swapchain(
+    instance: &Instance,
+    device: &Device,
+    physical_device: PhysicalDevice,
+    surface_loader: &surface::Instance,
+    surface: SurfaceKHR,
+    window: &Window,
+    old_swapchain: SwapchainKHR,
+) -> Result<SwapchainBundle> {
+    let capabilities = unsafe {
+        surface_loader.get_physical_device_surface_capabilities(physical_device, surface)
+    }?;
+    let formats =
+        unsafe { surface_loader.get_physical_device_surface_formats(physical_device, surface) }?;
+    let present_modes = unsafe {
+        surface_loader.get_physical_device_surface_present_modes(physical_device, surface)
+    }?;
+
+    let surface_format = formats
+        .iter()
+        .find(|format| {
+            format.format == vk::Format::B8G8R8A8_UNORM
+                && format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
+        })
+        .or_else(|| formats.first())
+        .copied()
+        .context("Surface has no supported formats")?;
+
+    let present_mode = present_modes
+        .iter()
+        .copied()
+        .find(|&mode| mode == vk::PresentModeKHR::MAILBOX)
+        .unwrap_or(vk::PresentModeKHR::FIFO);
+
+    let extent = if capabilities.current_extent.width != u32::MAX {
+        capabilities.current_extent
+    } else {
+        let size = window.inner_size();
+        Extent2D {
+            width: size.width.clamp(
+                capabilities.min_image_extent.width,
+                capabilities.max_image_extent.width,
+            ),
+            height: size.height.clamp(
+                capabilities.min_image_extent.height,
+                capabilities.max_image_extent.height,
+            ),
+        }
+    };
+
+    let mut image_count = capabilities.min_image_count + 1;
+    if capabilities.max_image_count > 0 {
+        image_count = image_count.min(capabilities.max_image_count);
+    }
+
+    let swapchain_loader = swapchain::Device::new(instance, device);
+
+    let create_info = vk::SwapchainCreateInfoKHR::default()
+        .surface(surface)
+        .min_image_count(image_count)
+        .image_format(surface_format.format)
+        .image_color_space(surface_format.color_space)
+        .image_extent(extent)
+        .image_array_layers(1)
+        .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST)
+        .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
+        .pre_transform(capabilities.current_transform)
+        .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+        .present_mode(present_mode)
+        .clipped(true)
+        .old_swapchain(old_swapchain);
+
+    let swapchain = unsafe { swapchain_loader.create_swapchain(&create_info, None) }?;
+    let images = unsafe { swapchain_loader.get_swapchain_images(swapchain) }?;
+
+    let image_available_semaphore =
+        unsafe { device.create_semaphore(&vk::SemaphoreCreateInfo::default(), None) }?;
+
+    Ok(SwapchainBundle {
+        swapchain_loader,
+        swapchain,
+        images,
+        extent,
+        image_available_semaphore,
+    })
+}
+
+fn destroy_swapchain(device: &Device, bundle: &SwapchainBundle) {
+    unsafe { device.destroy_semaphore(bundle.image_available_semaphore, None) };
+    unsafe {
+        bundle
+            .swapchain_loader
+            .destroy_swapchain(bundle.swapchain, None)
+    };
+}
+
+// The device-local image the dungeon is copied into before it's blitted onto
+// the swapchain image.
+struct PreviewImage {
+    image: Image,
+    allocation: Allocation,
+}
+
+fn create_preview_image(
+    device: &Device,
+    allocator: &mut Allocator,
+    width: u32,
+    height: u32,
+) -> Result<PreviewImage> {
+    let create_info = vk::ImageCreateInfo::default()
+        .image_type(vk::ImageType::TYPE_2D)
+        .format(vk::Format::R8G8B8A8_UNORM)
+        .extent(vk::Extent3D {
+            width,
+            height,
+            depth: 1,
+        })
+        .mip_levels(1)
+        .array_layers(1)
+        .samples(vk::SampleCountFlags::TYPE_1)
+        .tiling(vk::ImageTiling::OPTIMAL)
+        .usage(vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::TRANSFER_SRC)
+        .sharing_mode(vk::SharingMode::EXCLUSIVE)
+        .initial_layout(vk::ImageLayout::UNDEFINED);
+
+    let image = unsafe { device.create_image(&create_info, None) }?;
+    let requirements = unsafe { device.get_image_memory_requirements(image) };
+
+    let allocation = allocator.allocate(&AllocationCreateDesc {
+        name: "Dungeon preview image",
+        requirements,
+        location: MemoryLocation::GpuOnly,
+        linear: false,
+        allocation_scheme: AllocationScheme::GpuAllocatorManaged,
+    })?;
+
+    unsafe { device.bind_image_memory(image, allocation.memory(), allocation.offset()) }?;
+
+    Ok(PreviewImage { image, allocation })
+}
+
+fn color_subresource_range() -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange::default()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_mip_level(0)
+        .level_count(1)
+        .base_array_layer(0)
+        .layer_count(1)
+}
+
+fn color_subresource_layers() -> vk::ImageSubresourceLayers {
+    vk::ImageSubresourceLayers::default()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .mip_level(0)
+        .base_array_layer(0)
+        .layer_count(1)
+}
+
+// Regenerates (if `regenerate`) and presents a single preview frame.
+#[allow(clippy::too_many_arguments)]
+fn present_frame(
+    device: &Device,
+    queue: Queue,
+    swapchain_bundle: &SwapchainBundle,
+    command_buffer: CommandBuffer,
+    render_fence: Fence,
+    render_finished_semaphore: Semaphore,
+    buffer: Buffer,
+    preview_image: &PreviewImage,
+    pipeline: Pipeline,
+    pipeline_layout: PipelineLayout,
+    descriptor_set: DescriptorSet,
+    width: u32,
+    height: u32,
+    cell_size: u32,
+    seed: u32,
+    regenerate: bool,
+) -> Result<()> {
+    unsafe { device.wait_for_fences(&[render_fence], true, u64::MAX) }?;
+
+    let (image_index, _) = unsafe {
+        swapchain_bundle.swapchain_loader.acquire_next_image(
+            swapchain_bundle.swapchain,
+            u64::MAX,
+            swapchain_bundle.image_available_semaphore,
+            Fence::null(),
+        )
+    }?;
+
+    unsafe { device.reset_fences(&[render_fence]) }?;
+    unsafe { device.reset_command_buffer(command_buffer, vk::CommandBufferResetFlags::empty()) }?;
+
+    let begin_info =
+        CommandBufferBeginInfo::default().flags(CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+    unsafe { device.begin_command_buffer(command_buffer, &begin_info) }?;
+
+    if regenerate {
+        let push_constants = DungeonPushConstants {
+            width,
+            height,
+            seed,
+            cell_size,
+        };
+
+        unsafe {
+            device.cmd_bind_pipeline(command_buffer, PipelineBindPoint::COMPUTE, pipeline);
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                PipelineBindPoint::COMPUTE,
+                pipeline_layout,
+                0,
+                &[descriptor_set],
+                &[],
+            );
+            device.cmd_push_constants(
+                command_buffer,
+                pipeline_layout,
+                ShaderStageFlags::COMPUTE,
+                0,
+                std::slice::from_raw_parts(
+                    &push_constants as *const DungeonPushConstants as *const u8,
+                    std::mem::size_of::<DungeonPushConstants>(),
+                ),
+            );
+            device.cmd_dispatch(command_buffer, width.div_ceil(16), height.div_ceil(16), 1);
+
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[vk::BufferMemoryBarrier::default()
+                    .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+                    .dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+                    .buffer(buffer)
+                    .offset(0)
+                    .size(vk::WHOLE_SIZE)],
+                &[vk::ImageMemoryBarrier::default()
+                    .old_layout(vk::ImageLayout::UNDEFINED)
+                    .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .image(preview_image.image)
+                    .subresource_range(color_subresource_range())],
+            );
+
+            device.cmd_copy_buffer_to_image(
+                command_buffer,
+                buffer,
+                preview_image.image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[vk::BufferImageCopy::default()
+                    .buffer_offset(0)
+                    .image_subresource(color_subresource_layers())
+                    .image_extent(vk::Extent3D {
+                        width,
+                        height,
+                        depth: 1,
+                    })],
+            );
+
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier::default()
+                    .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                    .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .image(preview_image.image)
+                    .subresource_range(color_subresource_range())],
+            );
+        }
+    }
+
+    let swapchain_image = swapchain_bundle.images[image_index as usize];
+
+    unsafe {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[vk::ImageMemoryBarrier::default()
+                .old_layout(vk::ImageLayout::UNDEFINED)
+                .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(swapchain_image)
+                .subresource_range(color_subresource_range())],
+        );
+
+        device.cmd_blit_image(
+            command_buffer,
+            preview_image.image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            swapchain_image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[vk::ImageBlit::default()
+                .src_subresource(color_subresource_layers())
+                .src_offsets([
+                    vk::Offset3D::default(),
+                    vk::Offset3D {
+                        x: width as i32,
+                        y: height as i32,
+                        z: 1,
+                    },
+                ])
+                .dst_subresource(color_subresource_layers())
+                .dst_offsets([
+                    vk::Offset3D::default(),
+                    vk::Offset3D {
+                        x: swapchain_bundle.extent.width as i32,
+                        y: swapchain_bundle.extent.height as i32,
+                        z: 1,
+                    },
+                ])],
+            vk::Filter::NEAREST,
+        );
+
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[vk::ImageMemoryBarrier::default()
+                .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                .new_layout(vk::ImageLayout::PRESENT_SRC_KHR)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(swapchain_image)
+                .subresource_range(color_subresource_range())],
+        );
+    }
+
+    unsafe { device.end_command_buffer(command_buffer) }?;
+
+    let wait_semaphores = [swapchain_bundle.image_available_semaphore];
+    let signal_semaphores = [render_finished_semaphore];
+    let wait_stages = [vk::PipelineStageFlags::TRANSFER];
+    let command_buffers = [command_buffer];
+    let submit_info = SubmitInfo::default()
+        .wait_semaphores(&wait_semaphores)
+        .wait_dst_stage_mask(&wait_stages)
+        .command_buffers(&command_buffers)
+        .signal_semaphores(&signal_semaphores);
+
+    unsafe { device.queue_submit(queue, &[submit_info], render_fence) }?;
+
+    let swapchains = [swapchain_bundle.swapchain];
+    let image_indices = [image_index];
+    let present_info = vk::PresentInfoKHR::default()
+        .wait_semaphores(&signal_semaphores)
+        .swapchains(&swapchains)
+        .image_indices(&image_indices);
+
+    unsafe {
+        swapchain_bundle
+            .swapchain_loader
+            .queue_present(queue, &present_info)
+    }?;
+
+    Ok(())
+}
+
+// Opens a window and regenerates the dungeon with a fresh seed on Space.
+#[allow(clippy::too_many_arguments)]
+fn run_live_preview(
+    entry: &Entry,
+    instance: &Instance,
+    physical_device: PhysicalDevice,
+    compute_queue_family_index: u32,
+    device: &Device,
+    queue: Queue,
+    allocator: &mut Allocator,
+    command_pool: CommandPool,
+    buffer: Buffer,
+    pipeline: Pipeline,
+    pipeline_layout: PipelineLayout,
+    descriptor_set: DescriptorSet,
+    width: u32,
+    height: u32,
+    cell_size: u32,
+    event_loop: EventLoop<()>,
+    window: Window,
+) -> Result<()> {
+    let surface_loader = surface::Instance::new(entry, instance);
+    let surface: SurfaceKHR = unsafe {
+        ash_window::create_surface(
+            entry,
+            instance,
+            window.display_handle()?.as_raw(),
+            window.window_handle()?.as_raw(),
+            None,
+        )
+    }?;
+
+    let present_supported = unsafe {
+        surface_loader.get_physical_device_surface_support(
+            physical_device,
+            compute_queue_family_index,
+            surface,
+        )
+    }?;
+    if !present_supported {
+        anyhow::bail!("Compute queue family does not support presentation to this surface");
+    }
+
+    let mut swapchain_bundle = create_swapchain(
+        instance,
+        device,
+        physical_device,
+        &surface_loader,
+        surface,
+        &window,
+        SwapchainKHR::null(),
+    )?;
+
+    let mut preview_image = Some(create_preview_image(device, allocator, width, height)?);
+
+    let command_buffer = {
+        let create_info = CommandBufferAllocateInfo::default()
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_pool(command_pool)
+            .command_buffer_count(1);
+        unsafe { device.allocate_command_buffers(&create_info) }?
+            .into_iter()
+            .next()
+            .context("No command buffer allocated for the preview loop")?
+    };
+
+    let render_fence = unsafe {
+        device.create_fence(
+            &FenceCreateInfo::default().flags(FenceCreateFlags::SIGNALED),
+            None,
+        )
+    }?;
+    let render_finished_semaphore =
+        unsafe { device.create_semaphore(&vk::SemaphoreCreateInfo::default(), None) }?;
+
+    let mut seed: u32 = rand::thread_rng().gen();
+    let mut needs_regenerate = true;
+
+    window.request_redraw();
+
+    event_loop.run(move |event, elwt| {
+        elwt.set_control_flow(ControlFlow::Wait);
+
+        match event {
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => {
+                unsafe { device.device_wait_idle() }.ok();
+                unsafe { device.destroy_semaphore(render_finished_semaphore, None) };
+                unsafe { device.destroy_fence(render_fence, None) };
+                if let Some(preview_image) = preview_image.take() {
+                    unsafe { device.destroy_image(preview_image.image, None) };
+                    if let Err(err) = allocator.free(preview_image.allocation) {
+                        error!("Failed to free preview image allocation: {err:?}");
+                    }
+                }
+                destroy_swapchain(device, &swapchain_bundle);
+                unsafe { surface_loader.destroy_surface(surface, None) };
+                elwt.exit();
+            }
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                physical_key: PhysicalKey::Code(KeyCode::Space),
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => {
+                seed = rand::thread_rng().gen();
+                needs_regenerate = true;
+                window.request_redraw();
+            }
+            Event::WindowEvent {
+                event: WindowEvent::Resized(_),
+                ..
+            } => {
+                unsafe { device.device_wait_idle() }.ok();
+                destroy_swapchain(device, &swapchain_bundle);
+                match create_swapchain(
+                    instance,
+                    device,
+                    physical_device,
+                    &surface_loader,
+                    surface,
+                    &window,
+                    SwapchainKHR::null(),
+                ) {
+                    Ok(bundle) => swapchain_bundle = bundle,
+                    Err(err) => {
+                        error!("Failed to recreate swapchain: {err:?}");
+                        elwt.exit();
+                    }
+                }
+                needs_regenerate = true;
+                window.request_redraw();
+            }
+            Event::WindowEvent {
+                event: WindowEvent::RedrawRequested,
+                ..
+            } => {
+                if let Some(preview_image) = &preview_image {
+                    if let Err(err) = present_frame(
+                        device,
+                        queue,
+                        &swapchain_bundle,
+                        command_buffer,
+                        render_fence,
+                        render_finished_semaphore,
+                        buffer,
+                        preview_image,
+                        pipeline,
+                        pipeline_layout,
+                        descriptor_set,
+                        width,
+                        height,
+                        cell_size,
+                        seed,
+                        needs_regenerate,
+                    ) {
+                        error!("Failed to present a preview frame: {err:?}");
+                    }
+                    needs_regenerate = false;
+                }
+            }
+            _ => {}
+        }
+    })?;
+
+    Ok(())
+}
+
 fn main() -> Result<()> {
+    env_logger::init();
+
     // Data
     let width: u64 = 1280;
     let height: u64 = 720;
     let value_count: u64 = width * height;
-    let red: u32 = rand::thread_rng().gen_range(0..255);
-    let green: u32 = rand::thread_rng().gen_range(0..255);
-    let blue: u32 = rand::thread_rng().gen_range(0..255);
-    let alpha: u32 = 255;
-    let value: u32 = red | green << 8 | blue << 16 | alpha << 24;
+    let seed: u32 = rand::thread_rng().gen();
+    let cell_size: u32 = 4;
+
+    // The window has to exist before the instance, since the required surface
+    // extensions depend on the platform it was opened on.
+    let live: bool = std::env::args().any(|arg| arg == "--live");
+    let (event_loop, window) = if live {
+        let event_loop = EventLoop::new()?;
+        let window = WindowBuilder::new()
+            .with_title("rsDungeon - press Space to regenerate")
+            .with_inner_size(winit::dpi::PhysicalSize::new(width as u32, height as u32))
+            .build(&event_loop)?;
+        (Some(event_loop), Some(window))
+    } else {
+        (None, None)
+    };
 
     // Ash setup
     let entry: Entry = unsafe { ash::Entry::load() }?;
 
     // Enable validation layer
 
+    let debug_create_info: DebugUtilsMessengerCreateInfoEXT =
+        vk::DebugUtilsMessengerCreateInfoEXT::default()
+            .message_severity(
+                vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE
+                    | vk::DebugUtilsMessageSeverityFlagsEXT::INFO
+                    | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                    | vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            )
+            .message_type(
+                vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                    | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
+                    | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
+            )
+            .pfn_user_callback(Some(vulkan_debug_utils_callback));
+
     // Setup Instance
     let instance: Instance = {
         let application_name = CString::new(env!("CARGO_PKG_NAME")).unwrap();
@@ -94,11 +882,26 @@ fn main() -> Result<()> {
         let mut create_info: InstanceCreateInfo =
             vk::InstanceCreateInfo::default().application_info(&application_info);
 
-        // Set up the validation layer
+        let validation_layer_name: CString = CString::new("VK_LAYER_KHRONOS_validation").unwrap();
+        let validation_layer_names: Vec<*const i8> = vec![validation_layer_name.as_ptr()];
+
+        // Surface extensions only apply in --live mode; debug_utils only when validation is on.
+        let mut extension_names: Vec<*const i8> = Vec::new();
+        if let Some(window) = window.as_ref() {
+            let required = ash_window::enumerate_required_extensions(
+                window.display_handle()?.as_raw(),
+            )?;
+            extension_names.extend_from_slice(required);
+        }
         if (VALIDATION_ENABLED) {
-            let validation_layer_name: CString =
-                CString::new("VK_LAYER_KHRONOS_validation").unwrap();
+            extension_names.push(debug_utils::NAME.as_ptr());
+        }
+        if !extension_names.is_empty() {
+            create_info = create_info.enabled_extension_names(&extension_names);
+        }
 
+        // Set up the validation layer
+        if (VALIDATION_ENABLED) {
             unsafe {
                 let layer_properties = entry.enumerate_instance_layer_properties()?;
 
@@ -117,48 +920,51 @@ fn main() -> Result<()> {
                 }
             }
 
-            let debug_create_info: DebugUtilsMessengerCreateInfoEXT =
-                vk::DebugUtilsMessengerCreateInfoEXT::default()
-                    .message_severity(
-                        vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
-                            | vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
-                    )
-                    .message_type(
-                        vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
-                            | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
-                            | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
-                    )
-                    .pfn_user_callback(Some(vulkan_debug_utils_callback));
-
-            let validation_layer_names: Vec<*const i8> = vec![validation_layer_name.as_ptr()];
-
             create_info.p_next =
                 &debug_create_info as *const vk::DebugUtilsMessengerCreateInfoEXT as *const c_void;
-            create_info.enabled_layer_names(&validation_layer_names);
+            create_info = create_info.enabled_layer_names(&validation_layer_names);
         }
         unsafe { entry.create_instance(&create_info, None) }?
     };
 
+    // Create the debug messenger so `vulkan_debug_utils_callback` fires for the
+    // lifetime of the instance, not just during its creation/teardown.
+    let debug_utils_loader: debug_utils::Instance = debug_utils::Instance::new(&entry, &instance);
+    let debug_messenger: DebugUtilsMessengerEXT = if VALIDATION_ENABLED {
+        unsafe { debug_utils_loader.create_debug_utils_messenger(&debug_create_info, None) }?
+    } else {
+        DebugUtilsMessengerEXT::null()
+    };
+
     // Build Device
-    let physical_device: PhysicalDevice = unsafe { instance.enumerate_physical_devices() }?
-        .into_iter()
-        .next()
-        .context("No physical Device Found")?;
+    let PhysicalDeviceSelection {
+        physical_device,
+        compute_queue_family_index,
+    } = pick_physical_device(&instance)?;
 
     let device: Device = {
         let queue_priorities: [f32; 1] = [1.0];
         let queue_create_infos: [DeviceQueueCreateInfo; 1] = [DeviceQueueCreateInfo::default()
-            .queue_family_index(0)
+            .queue_family_index(compute_queue_family_index)
             .queue_priorities(&queue_priorities)];
 
-        let create_info: DeviceCreateInfo =
+        let device_extension_names: Vec<*const i8> = if live {
+            vec![swapchain::NAME.as_ptr()]
+        } else {
+            Vec::new()
+        };
+
+        let mut create_info: DeviceCreateInfo =
             vk::DeviceCreateInfo::default().queue_create_infos(&queue_create_infos);
+        if !device_extension_names.is_empty() {
+            create_info = create_info.enabled_extension_names(&device_extension_names);
+        }
 
         unsafe { instance.create_device(physical_device, &create_info, None) }?
     };
 
     // Setup Queue
-    let queue: Queue = unsafe { device.get_device_queue(0, 0) };
+    let queue: Queue = unsafe { device.get_device_queue(compute_queue_family_index, 0) };
 
     // Set up Buffer
     let mut allocator = Allocator::new(&AllocatorCreateDesc {
@@ -170,10 +976,16 @@ fn main() -> Result<()> {
         allocation_sizes: Default::default(),
     })?;
 
+    // Live preview never reads `buffer` from the host, so it always prefers
+    // device-local memory; the headless path also needs a staging buffer
+    // unless device-local memory happens to be host-visible.
+    let needs_staging = live || !device_local_memory_is_host_visible(&instance, physical_device);
+    let buffer_size = value_count * std::mem::size_of::<u32>() as vk::DeviceSize;
+
     let buffer: Buffer = {
         let create_info: BufferCreateInfo = vk::BufferCreateInfo::default()
-            .size(value_count * std::mem::size_of::<u32>() as vk::DeviceSize)
-            .usage(vk::BufferUsageFlags::TRANSFER_DST);
+            .size(buffer_size)
+            .usage(vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::TRANSFER_SRC);
 
         unsafe { device.create_buffer(&create_info, None) }?
     };
@@ -183,9 +995,13 @@ fn main() -> Result<()> {
             unsafe { device.get_buffer_memory_requirements(buffer) };
 
         let allocation_create_description = AllocationCreateDesc {
-            name: "Example allocation",
+            name: "Dungeon buffer",
             requirements: memory_requirements,
-            location: MemoryLocation::GpuToCpu,
+            location: if needs_staging {
+                MemoryLocation::GpuOnly
+            } else {
+                MemoryLocation::GpuToCpu
+            },
             linear: true, // Buffers are always linear
             allocation_scheme: AllocationScheme::GpuAllocatorManaged,
         };
@@ -197,88 +1013,238 @@ fn main() -> Result<()> {
         allocation
     };
 
+    // The staging buffer only exists for the headless readback path; the
+    // live preview copies `buffer` straight into an image on the GPU.
+    let staging: Option<(Buffer, Allocation)> = if needs_staging && !live {
+        let staging_buffer: Buffer = {
+            let create_info: BufferCreateInfo = vk::BufferCreateInfo::default()
+                .size(buffer_size)
+                .usage(vk::BufferUsageFlags::TRANSFER_DST);
+
+            unsafe { device.create_buffer(&create_info, None) }?
+        };
+
+        let staging_allocation: Allocation = {
+            let memory_requirements: vk::MemoryRequirements =
+                unsafe { device.get_buffer_memory_requirements(staging_buffer) };
+
+            let allocation = allocator.allocate(&AllocationCreateDesc {
+                name: "Dungeon staging buffer",
+                requirements: memory_requirements,
+                location: MemoryLocation::GpuToCpu,
+                linear: true,
+                allocation_scheme: AllocationScheme::GpuAllocatorManaged,
+            })?;
+
+            unsafe {
+                device.bind_buffer_memory(staging_buffer, allocation.memory(), allocation.offset())
+            };
+
+            allocation
+        };
+
+        Some((staging_buffer, staging_allocation))
+    } else {
+        None
+    };
+
+    // Set up the dungeon-generation compute pipeline
+    let ComputePipelineResources {
+        shader_module,
+        descriptor_set_layout,
+        descriptor_pool,
+        descriptor_set,
+        pipeline_layout,
+        pipeline,
+    } = create_compute_pipeline(&device, buffer)?;
+
     // Setup CommandPool
     let command_pool: CommandPool = {
         let create_info: CommandPoolCreateInfo =
-            vk::CommandPoolCreateInfo::default().queue_family_index(0);
+            vk::CommandPoolCreateInfo::default().queue_family_index(compute_queue_family_index);
 
         unsafe { device.create_command_pool(&create_info, None) }?
     };
 
-    // Create Buffers
-    let command_buffer: CommandBuffer = {
-        let create_info: CommandBufferAllocateInfo = vk::CommandBufferAllocateInfo::default()
-            .level(vk::CommandBufferLevel::PRIMARY)
-            .command_pool(command_pool)
-            .command_buffer_count(1);
+    if live {
+        let event_loop = event_loop.context("event loop missing for --live")?;
+        let window = window.context("window missing for --live")?;
+        run_live_preview(
+            &entry,
+            &instance,
+            physical_device,
+            compute_queue_family_index,
+            &device,
+            queue,
+            &mut allocator,
+            command_pool,
+            buffer,
+            pipeline,
+            pipeline_layout,
+            descriptor_set,
+            width as u32,
+            height as u32,
+            cell_size,
+            event_loop,
+            window,
+        )?;
+    } else {
+        // Create Buffers
+        let command_buffer: CommandBuffer = {
+            let create_info: CommandBufferAllocateInfo = vk::CommandBufferAllocateInfo::default()
+                .level(vk::CommandBufferLevel::PRIMARY)
+                .command_pool(command_pool)
+                .command_buffer_count(1);
+
+            unsafe {
+                device
+                    .allocate_command_buffers(&create_info)?
+                    .into_iter()
+                    .next()
+                    .context("No Command Buffers")
+            }?
+        };
+
+        // Recording Command Buffer
+        {
+            let begin_info: CommandBufferBeginInfo = vk::CommandBufferBeginInfo::default()
+                .flags(CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+            unsafe { device.begin_command_buffer(command_buffer, &begin_info) }?;
+        }
+
+        let push_constants = DungeonPushConstants {
+            width: width as u32,
+            height: height as u32,
+            seed,
+            cell_size,
+        };
 
         unsafe {
-            device
-                .allocate_command_buffers(&create_info)?
-                .into_iter()
-                .next()
-                .context("No Command Buffers")
-        }?
-    };
+            device.cmd_bind_pipeline(command_buffer, PipelineBindPoint::COMPUTE, pipeline);
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                PipelineBindPoint::COMPUTE,
+                pipeline_layout,
+                0,
+                &[descriptor_set],
+                &[],
+            );
+            device.cmd_push_constants(
+                command_buffer,
+                pipeline_layout,
+                ShaderStageFlags::COMPUTE,
+                0,
+                std::slice::from_raw_parts(
+                    &push_constants as *const DungeonPushConstants as *const u8,
+                    std::mem::size_of::<DungeonPushConstants>(),
+                ),
+            );
+            device.cmd_dispatch(
+                command_buffer,
+                (width as u32).div_ceil(16),
+                (height as u32).div_ceil(16),
+                1,
+            );
+        }
 
-    // Recording Command Buffer
-    {
-        let begin_info: CommandBufferBeginInfo =
-            vk::CommandBufferBeginInfo::default().flags(CommandBufferUsageFlags::ONE_TIME_SUBMIT);
-        unsafe { device.begin_command_buffer(command_buffer, &begin_info) }?;
-    }
+        if let Some((staging_buffer, _)) = &staging {
+            unsafe {
+                device.cmd_pipeline_barrier(
+                    command_buffer,
+                    vk::PipelineStageFlags::COMPUTE_SHADER,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[vk::BufferMemoryBarrier::default()
+                        .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+                        .dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+                        .buffer(buffer)
+                        .offset(0)
+                        .size(vk::WHOLE_SIZE)],
+                    &[],
+                );
+                device.cmd_copy_buffer(
+                    command_buffer,
+                    buffer,
+                    *staging_buffer,
+                    &[vk::BufferCopy::default()
+                        .src_offset(0)
+                        .dst_offset(0)
+                        .size(buffer_size)],
+                );
+            }
+        }
 
-    unsafe {
-        device.cmd_fill_buffer(
-            command_buffer,
-            buffer,
-            allocation.offset(),
-            allocation.size(),
-            value,
+        unsafe { device.end_command_buffer(command_buffer) }?;
+
+        // Execute Command Buffer
+        let fence: Fence = {
+            let create_info: FenceCreateInfo = vk::FenceCreateInfo::default();
+            unsafe { device.create_fence(&create_info, None) }?
+        };
+
+        {
+            let submit_info: SubmitInfo =
+                vk::SubmitInfo::default().command_buffers(std::slice::from_ref(&command_buffer));
+            unsafe { device.queue_submit(queue, std::slice::from_ref(&submit_info), fence) };
+        }
+
+        // Wait for execution. This also times the staging copy, if any,
+        // since it's recorded into the same command buffer.
+        println!(
+            "Using the {} buffer path",
+            if needs_staging {
+                "device-local + staging"
+            } else {
+                "direct host-visible"
+            }
         );
-    }
+        let gpu_start: Instant = std::time::Instant::now();
+        unsafe { device.wait_for_fences(std::slice::from_ref(&fence), true, u64::MAX) }?;
+        println!("GPU took {:?}", std::time::Instant::now() - gpu_start);
 
-    unsafe { device.end_command_buffer(command_buffer) }?;
+        // Read back
+        let data: &[u8] = match &staging {
+            Some((_, staging_allocation)) => staging_allocation
+                .mapped_slice()
+                .context("Host cannot access staging buffer")?,
+            None => allocation
+                .mapped_slice()
+                .context("Host cannot access buffer")?,
+        };
 
-    // Execute Command Buffer
-    let fence: Fence = {
-        let create_info: FenceCreateInfo = vk::FenceCreateInfo::default();
-        unsafe { device.create_fence(&create_info, None) }?
-    };
+        let png_start: Instant = std::time::Instant::now();
+        image::save_buffer(
+            "tmp/image.png",
+            data,
+            width as u32,
+            height as u32,
+            image::ColorType::Rgba8,
+        );
+        println!("PNG took {:?}", std::time::Instant::now() - png_start);
 
-    {
-        let submit_info: SubmitInfo =
-            vk::SubmitInfo::default().command_buffers(std::slice::from_ref(&command_buffer));
-        unsafe { device.queue_submit(queue, std::slice::from_ref(&submit_info), fence) };
-    }
+        unsafe { device.destroy_fence(fence, None) };
 
-    // Wait for execution
-    let gpu_start: Instant = std::time::Instant::now();
-    unsafe { device.wait_for_fences(std::slice::from_ref(&fence), true, u64::MAX) }?;
-    println!("GPU took {:?}", std::time::Instant::now() - gpu_start);
-
-    // Read back
-    let data: &[u8] = allocation
-        .mapped_slice()
-        .context("Host cannot access buffer")?;
-
-    let png_start: Instant = std::time::Instant::now();
-    image::save_buffer(
-        "tmp/image.png",
-        data,
-        width as u32,
-        height as u32,
-        image::ColorType::Rgba8,
-    );
-    println!("PNG took {:?}", std::time::Instant::now() - png_start);
+        if let Some((staging_buffer, staging_allocation)) = staging {
+            allocator.free(staging_allocation)?;
+            unsafe { device.destroy_buffer(staging_buffer, None) };
+        }
+    }
 
     // Cleanup
-    unsafe { device.destroy_fence(fence, None) };
     unsafe { device.destroy_command_pool(command_pool, None) }
+    unsafe { device.destroy_pipeline(pipeline, None) };
+    unsafe { device.destroy_pipeline_layout(pipeline_layout, None) };
+    unsafe { device.destroy_descriptor_pool(descriptor_pool, None) };
+    unsafe { device.destroy_descriptor_set_layout(descriptor_set_layout, None) };
+    unsafe { device.destroy_shader_module(shader_module, None) };
     allocator.free(allocation)?;
     drop(allocator);
     unsafe { device.destroy_buffer(buffer, None) };
     unsafe { device.destroy_device(None) }
+    if (VALIDATION_ENABLED) {
+        unsafe { debug_utils_loader.destroy_debug_utils_messenger(debug_messenger, None) };
+    }
     unsafe { instance.destroy_instance(None) }
     Ok(())
 }